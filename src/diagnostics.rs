@@ -0,0 +1,443 @@
+use std::io::IsTerminal;
+
+use crate::lexer::Span;
+
+const DEFAULT_WIDTH: usize = 100;
+
+const RED_BOLD: &str = "\x1b[1;31m";
+const BLUE_BOLD: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorFormat {
+    Human,
+    /// `file:line:col: error: msg`, one line per diagnostic.
+    Short,
+}
+
+#[derive(Debug)]
+pub struct Diagnostic {
+    message: String,
+    span: Option<Span>,
+}
+
+impl Diagnostic {
+    pub fn error(message: impl Into<String>, span: Span) -> Self {
+        Self {
+            message: message.into(),
+            span: Some(span),
+        }
+    }
+
+    /// An error not tied to any place in the source, e.g. an I/O failure.
+    pub fn bare(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            span: None,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+}
+
+/// A named source with its line starts indexed once, so locating each of
+/// many diagnostics is a binary search rather than a scan from the top.
+#[derive(Debug)]
+pub struct SourceFile<'a> {
+    name: &'a str,
+    src: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> SourceFile<'a> {
+    pub fn new(name: &'a str, src: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(src.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self {
+            name,
+            src,
+            line_starts,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        self.name
+    }
+
+    pub fn src(&self) -> &str {
+        self.src
+    }
+
+    /// 1-based line and column (in chars) of the byte offset `pos`.
+    fn line_col(&self, pos: usize) -> (usize, usize) {
+        let line = self.line_starts.partition_point(|&start| start <= pos);
+        let start = self.line_starts[line - 1];
+        (line, self.src[start..pos].chars().count() + 1)
+    }
+
+    /// Text of the 1-based `line`, without its line ending.
+    fn line(&self, line: usize) -> &str {
+        let start = self.line_starts[line - 1];
+        let end = self
+            .line_starts
+            .get(line)
+            .copied()
+            .unwrap_or(self.src.len());
+        let text = &self.src[start..end];
+        let text = text.strip_suffix('\n').unwrap_or(text);
+        text.strip_suffix('\r').unwrap_or(text)
+    }
+}
+
+#[derive(Debug)]
+pub struct Emitter {
+    color: bool,
+    format: ErrorFormat,
+    width: usize,
+}
+
+impl Emitter {
+    /// Resolves `Auto` against stderr being a terminal and `NO_COLOR`, and
+    /// wraps to the terminal width unless `COLUMNS` overrides it.
+    pub fn new(color: ColorChoice, format: ErrorFormat) -> Self {
+        let tty = std::io::stderr().is_terminal();
+        let color = match color {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            // NO_COLOR only counts when set to something non-empty
+            ColorChoice::Auto => tty && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()),
+        };
+        let width = std::env::var("COLUMNS")
+            .ok()
+            .and_then(|c| c.parse().ok())
+            .or_else(|| if tty { terminal_width() } else { None })
+            .unwrap_or(DEFAULT_WIDTH);
+        Self {
+            color,
+            format,
+            width,
+        }
+    }
+
+    pub fn emit(&self, file: &SourceFile, diag: &Diagnostic) {
+        eprint!("{}", self.render(file, diag));
+    }
+
+    pub fn render(&self, file: &SourceFile, diag: &Diagnostic) -> String {
+        let pos = diag.span.map(|span| (span, file.line_col(span.start())));
+
+        if self.format == ErrorFormat::Short {
+            return match pos {
+                Some((_, (line, col))) => {
+                    format!("{}:{}:{}: error: {}\n", file.name, line, col, diag.message)
+                }
+                None => format!("{}: error: {}\n", file.name, diag.message),
+            };
+        }
+
+        let mut out = String::new();
+        // continuation lines line up with the text after "error: "
+        let lines = wrap(&diag.message, self.width.saturating_sub(7));
+        out.push_str(&format!(
+            "{}: {}\n",
+            self.paint(RED_BOLD, "error"),
+            self.paint(BOLD, lines.first().map(String::as_str).unwrap_or(""))
+        ));
+        for l in lines.iter().skip(1) {
+            out.push_str(&format!("       {}\n", self.paint(BOLD, l)));
+        }
+
+        let Some((span, (line, col))) = pos else {
+            out.push_str(&format!(
+                " {} {}\n",
+                self.paint(BLUE_BOLD, "-->"),
+                file.name
+            ));
+            return out;
+        };
+        let gutter = " ".repeat(line.to_string().len());
+        let text = file.line(line);
+        // spans running past the end of the line are underlined up to it
+        let underlined = span.slice(file.src).lines().next().unwrap_or("");
+        let carets = "^".repeat(underlined.chars().count().max(1));
+        out.push_str(&format!(
+            "{}{} {}:{}:{}\n",
+            gutter,
+            self.paint(BLUE_BOLD, "-->"),
+            file.name,
+            line,
+            col
+        ));
+        out.push_str(&format!("{} {}\n", gutter, self.paint(BLUE_BOLD, "|")));
+        out.push_str(&format!(
+            "{} {} {}\n",
+            self.paint(BLUE_BOLD, &line.to_string()),
+            self.paint(BLUE_BOLD, "|"),
+            text
+        ));
+        out.push_str(&format!(
+            "{} {} {}{}\n",
+            gutter,
+            self.paint(BLUE_BOLD, "|"),
+            " ".repeat(col - 1),
+            self.paint(RED_BOLD, &carets)
+        ));
+        out
+    }
+
+    /// The closing line of a failing invocation.
+    pub fn summary(&self, errors: usize) {
+        let msg = if errors == 1 {
+            "aborting due to 1 previous error".to_string()
+        } else {
+            format!("aborting due to {} previous errors", errors)
+        };
+        eprintln!("{}: {}", self.paint(RED_BOLD, "error"), msg);
+    }
+
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", style, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+// TIOCGWINSZ differs per architecture (powerpc, mips and sparc use other
+// values), so the ioctl is only declared where the number is known.
+#[cfg(any(
+    all(
+        any(target_os = "linux", target_os = "android"),
+        any(
+            target_arch = "x86",
+            target_arch = "x86_64",
+            target_arch = "arm",
+            target_arch = "aarch64",
+            target_arch = "riscv64",
+            target_arch = "s390x",
+            target_arch = "loongarch64"
+        )
+    ),
+    all(
+        target_os = "macos",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    )
+))]
+fn terminal_width() -> Option<usize> {
+    use std::os::fd::AsRawFd;
+    use std::os::raw::{c_int, c_ulong};
+
+    #[repr(C)]
+    struct Winsize {
+        ws_row: u16,
+        ws_col: u16,
+        ws_xpixel: u16,
+        ws_ypixel: u16,
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    const TIOCGWINSZ: c_ulong = 0x5413;
+    #[cfg(target_os = "macos")]
+    const TIOCGWINSZ: c_ulong = 0x4008_7468;
+
+    extern "C" {
+        fn ioctl(fd: c_int, request: c_ulong, ...) -> c_int;
+    }
+
+    let mut size = Winsize {
+        ws_row: 0,
+        ws_col: 0,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    // SAFETY: TIOCGWINSZ only writes a `struct winsize` through the pointer.
+    let res = unsafe { ioctl(std::io::stderr().as_raw_fd(), TIOCGWINSZ, &mut size) };
+    (res == 0 && size.ws_col > 0).then_some(size.ws_col as usize)
+}
+
+#[cfg(not(any(
+    all(
+        any(target_os = "linux", target_os = "android"),
+        any(
+            target_arch = "x86",
+            target_arch = "x86_64",
+            target_arch = "arm",
+            target_arch = "aarch64",
+            target_arch = "riscv64",
+            target_arch = "s390x",
+            target_arch = "loongarch64"
+        )
+    ),
+    all(
+        target_os = "macos",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    )
+)))]
+fn terminal_width() -> Option<usize> {
+    None
+}
+
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for word in text.split_whitespace() {
+        if !current.is_empty() && current.len() + 1 + word.len() > width {
+            lines.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+    lines.push(current);
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::{Lexer, TokenKind};
+
+    fn unknown_token(src: &str) -> Diagnostic {
+        let lex = Lexer::new(src);
+        let t = lex
+            .tokens()
+            .iter()
+            .find(|t| t.kind() == TokenKind::Unknown)
+            .unwrap();
+        Diagnostic::error("unknown start of token", t.span())
+    }
+
+    #[test]
+    fn test_short_format() {
+        let src = "func main() {\n  jinbe $ = 1;\n}";
+        let emitter = Emitter {
+            color: false,
+            format: ErrorFormat::Short,
+            width: DEFAULT_WIDTH,
+        };
+
+        assert_eq!(
+            emitter.render(&SourceFile::new("main.lfy", src), &unknown_token(src)),
+            "main.lfy:2:9: error: unknown start of token\n"
+        );
+    }
+
+    #[test]
+    fn test_human_format() {
+        let src = "func main() {\n  jinbe $ = 1;\n}";
+        let emitter = Emitter {
+            color: false,
+            format: ErrorFormat::Human,
+            width: DEFAULT_WIDTH,
+        };
+
+        assert_eq!(
+            emitter.render(&SourceFile::new("main.lfy", src), &unknown_token(src)),
+            "error: unknown start of token\n \
+             --> main.lfy:2:9\n  \
+             |\n\
+             2 |   jinbe $ = 1;\n  \
+             |         ^\n"
+        );
+    }
+
+    #[test]
+    fn test_bare_names_file() {
+        let diag = Diagnostic::bare("couldn't read: not found");
+        let human = Emitter {
+            color: false,
+            format: ErrorFormat::Human,
+            width: DEFAULT_WIDTH,
+        };
+        let short = Emitter {
+            format: ErrorFormat::Short,
+            ..human
+        };
+
+        assert_eq!(
+            human.render(&SourceFile::new("main.lfy", ""), &diag),
+            "error: couldn't read: not found\n --> main.lfy\n"
+        );
+        assert_eq!(
+            short.render(&SourceFile::new("main.lfy", ""), &diag),
+            "main.lfy: error: couldn't read: not found\n"
+        );
+    }
+
+    #[test]
+    fn test_long_message_wraps() {
+        let src = "func main() {\n  jinbe $ = 1;\n}";
+        let lex = Lexer::new(src);
+        let t = lex.tokens()[0];
+        let diag = Diagnostic::error("the grand line is long and winding", t.span());
+        let emitter = Emitter {
+            color: false,
+            format: ErrorFormat::Human,
+            width: 24,
+        };
+
+        assert_eq!(
+            emitter.render(&SourceFile::new("main.lfy", src), &diag),
+            "error: the grand line is\n       \
+             long and winding\n \
+             --> main.lfy:1:1\n  \
+             |\n\
+             1 | func main() {\n  \
+             | ^^^^\n"
+        );
+    }
+
+    #[test]
+    fn test_color_choice() {
+        let src = "func main() {\n  jinbe $ = 1;\n}";
+        let file = SourceFile::new("main.lfy", src);
+        let diag = unknown_token(src);
+
+        let always = Emitter::new(ColorChoice::Always, ErrorFormat::Human);
+        let never = Emitter::new(ColorChoice::Never, ErrorFormat::Human);
+
+        assert!(always
+            .render(&file, &diag)
+            .starts_with("\x1b[1;31merror\x1b[0m: "));
+        assert!(!never.render(&file, &diag).contains('\x1b'));
+    }
+
+    #[test]
+    fn test_source_file_lines() {
+        let file = SourceFile::new("main.lfy", "ab\r\ncd\n\nλe");
+
+        assert_eq!(file.line_col(0), (1, 1));
+        assert_eq!(file.line_col(2), (1, 3));
+        assert_eq!(file.line_col(4), (2, 1));
+        assert_eq!(file.line_col(7), (3, 1));
+        assert_eq!(file.line_col(10), (4, 2));
+        assert_eq!(file.line(1), "ab");
+        assert_eq!(file.line(3), "");
+        assert_eq!(file.line(4), "λe");
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(wrap("one piece is real", 9), vec!["one piece", "is real"]);
+        assert_eq!(wrap("gomu", 2), vec!["gomu"]);
+    }
+}
@@ -0,0 +1,301 @@
+use crate::lexer::{Lexer, LiteralKind, TokenKind};
+
+const CREW: [&str; 9] = [
+    "luffy", "zoro", "nami", "usopp", "sanji", "chopper", "robin", "franky", "brook",
+];
+
+const PHRASES: [&str; 5] = [
+    "one piece",
+    "gomu gomu",
+    "king of the pirates",
+    "going merry",
+    "thousand sunny",
+];
+
+/// Deepest arithmetic nesting `GenConfig::with_max_depth` accepts. Every
+/// level may double the expression, so beyond this programs get huge and
+/// the recursive generator runs out of stack.
+pub const MAX_DEPTH: usize = 16;
+
+/// Xorshift generator, enough to drive the corpus and reproducible from a seed.
+#[derive(Debug)]
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // splitmix the seed so neighbouring seeds diverge straight away,
+        // and so the state is never the zero xorshift gets stuck on
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        Self((z ^ (z >> 31)) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct GenConfig {
+    seed: u64,
+    /// Number of statements in the body of `main`.
+    stmts: usize,
+    /// Maximum nesting of arithmetic expressions.
+    max_depth: usize,
+    arith: bool,
+    strings: bool,
+}
+
+impl GenConfig {
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn with_stmts(mut self, stmts: usize) -> Self {
+        self.stmts = stmts;
+        self
+    }
+
+    /// Clamped to [`MAX_DEPTH`].
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth.min(MAX_DEPTH);
+        self
+    }
+
+    pub fn with_arith(mut self, arith: bool) -> Self {
+        self.arith = arith;
+        self
+    }
+
+    pub fn with_strings(mut self, strings: bool) -> Self {
+        self.strings = strings;
+        self
+    }
+}
+
+impl Default for GenConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            stmts: 8,
+            max_depth: 3,
+            arith: true,
+            strings: true,
+        }
+    }
+}
+
+/// Produces random well-typed programs: integers are bound with `jinbe`
+/// and combined arithmetically, strings only ever reach `jet_pistol`.
+#[derive(Debug)]
+pub struct Generator {
+    rng: Rng,
+    config: GenConfig,
+}
+
+impl Generator {
+    pub fn new(config: GenConfig) -> Self {
+        Self {
+            rng: Rng::new(config.seed),
+            config,
+        }
+    }
+
+    pub fn program(&mut self) -> String {
+        let mut bound: Vec<&str> = Vec::new();
+        let mut out = String::from("func main() {\n");
+
+        for _ in 0..self.config.stmts {
+            match self.rng.below(3) {
+                0 if bound.len() < CREW.len() => {
+                    let name = CREW[bound.len()];
+                    let expr = self.int_expr(&bound, 0);
+                    out.push_str(&format!("  jinbe {} = {};\n", name, expr));
+                    bound.push(name);
+                }
+                1 if self.config.strings => {
+                    let phrase = PHRASES[self.rng.below(PHRASES.len())];
+                    out.push_str(&format!("  jet_pistol(\"{}\");\n", phrase));
+                }
+                _ => {
+                    let expr = self.int_expr(&bound, 0);
+                    out.push_str(&format!("  jet_pistol({});\n", expr));
+                }
+            }
+        }
+
+        out.push_str("}\n");
+        out
+    }
+
+    fn int_expr(&mut self, bound: &[&str], depth: usize) -> String {
+        if self.config.arith && depth < self.config.max_depth && self.rng.below(2) == 0 {
+            let op = ["+", "-", "*"][self.rng.below(3)];
+            let lhs = self.int_expr(bound, depth + 1);
+            let rhs = self.int_expr(bound, depth + 1);
+            return format!("{} {} {}", lhs, op, rhs);
+        }
+
+        if !bound.is_empty() && self.rng.below(2) == 0 {
+            bound[self.rng.below(bound.len())].to_string()
+        } else {
+            self.rng.below(100).to_string()
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Mutation {
+    /// Replace one of `+`, `-`, `*` with a different one.
+    SwapOperator,
+    /// Nudge one integer constant by one.
+    TweakConstant,
+}
+
+/// Applies `mutation` to a single site picked from `src` using `seed`.
+/// Returns `None` when the program has no site the mutation applies to;
+/// integer literals too wide for a `u64` are not sites.
+pub fn mutate(src: &str, mutation: Mutation, seed: u64) -> Option<String> {
+    let lex = Lexer::new(src);
+    let tokens = lex.tokens();
+    let sites: Vec<usize> = tokens
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| match mutation {
+            Mutation::SwapOperator => {
+                matches!(
+                    t.kind(),
+                    TokenKind::Plus | TokenKind::Minus | TokenKind::Star
+                )
+            }
+            Mutation::TweakConstant => {
+                matches!(
+                    t.kind(),
+                    TokenKind::Literal {
+                        kind: LiteralKind::Int,
+                        ..
+                    }
+                ) && t.span().slice(src).parse::<u64>().is_ok()
+            }
+        })
+        .map(|(i, _)| i)
+        .collect();
+
+    if sites.is_empty() {
+        return None;
+    }
+
+    let mut rng = Rng::new(seed);
+    let site = sites[rng.below(sites.len())];
+    let mut out = String::with_capacity(src.len() + 1);
+
+    for (i, t) in tokens.iter().enumerate() {
+        let text = t.span().slice(src);
+        if i != site {
+            out.push_str(text);
+            continue;
+        }
+        match t.kind() {
+            TokenKind::Plus => out.push_str(["-", "*"][rng.below(2)]),
+            TokenKind::Minus => out.push_str(["+", "*"][rng.below(2)]),
+            TokenKind::Star => out.push_str(["+", "-"][rng.below(2)]),
+            _ => {
+                let value: u64 = text.parse().ok()?;
+                let tweaked = if rng.below(2) == 0 {
+                    value.checked_add(1).or(value.checked_sub(1))
+                } else {
+                    value.checked_sub(1).or(value.checked_add(1))
+                };
+                out.push_str(&tweaked?.to_string());
+            }
+        }
+    }
+
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_programs_lex_cleanly() {
+        for seed in 0..50 {
+            let program = Generator::new(GenConfig::default().with_seed(seed)).program();
+            let lex = Lexer::new(&program);
+
+            assert!(lex.tokens().iter().all(|t| match t.kind() {
+                TokenKind::Unknown => false,
+                TokenKind::Literal {
+                    kind: LiteralKind::Str { terminated },
+                    ..
+                } => terminated,
+                _ => true,
+            }));
+        }
+    }
+
+    #[test]
+    fn test_same_seed_same_program() {
+        let config = GenConfig::default().with_seed(42);
+
+        assert_eq!(
+            Generator::new(config).program(),
+            Generator::new(config).program()
+        );
+    }
+
+    #[test]
+    fn test_max_depth_clamped() {
+        let config = GenConfig::default().with_max_depth(100_000).with_stmts(200);
+
+        assert_eq!(config.max_depth, MAX_DEPTH);
+        Generator::new(config).program();
+    }
+
+    #[test]
+    fn test_mutate() {
+        let program = "func main() { jinbe foo = 1 + 2; jet_pistol(foo); }";
+
+        let swapped = mutate(program, Mutation::SwapOperator, 0).unwrap();
+        assert_ne!(swapped, program);
+        assert_eq!(swapped.len(), program.len());
+
+        let tweaked = mutate(program, Mutation::TweakConstant, 0).unwrap();
+        assert_ne!(tweaked, program);
+
+        assert_eq!(mutate("func main() {}", Mutation::SwapOperator, 0), None);
+
+        for seed in 0..8 {
+            assert_eq!(
+                mutate("0", Mutation::TweakConstant, seed).as_deref(),
+                Some("1")
+            );
+            assert_eq!(
+                mutate("18446744073709551615", Mutation::TweakConstant, seed).as_deref(),
+                Some("18446744073709551614")
+            );
+            let tweaked = mutate("99999999999999999999 + 1", Mutation::TweakConstant, seed);
+            assert!(matches!(
+                tweaked.as_deref(),
+                Some("99999999999999999999 + 0") | Some("99999999999999999999 + 2")
+            ));
+        }
+        assert_eq!(
+            mutate("99999999999999999999", Mutation::TweakConstant, 0),
+            None
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use std::io::{self, Read};
 use std::str::Chars;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord)]
@@ -7,15 +8,25 @@ pub struct Span {
 }
 
 impl Span {
+    pub fn start(&self) -> usize {
+        self.start
+    }
+    pub fn end(&self) -> usize {
+        self.end
+    }
     pub fn len(&self) -> usize {
         self.end - self.start
     }
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
     pub fn slice<'s>(&self, src: &'s str) -> &'s str {
         &src[self.start..self.end]
     }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[non_exhaustive]
 pub enum LiteralKind {
     Char,
     Str { terminated: bool },
@@ -24,6 +35,7 @@ pub enum LiteralKind {
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[non_exhaustive]
 pub enum TokenKind {
     Ident,
     Literal {
@@ -55,12 +67,13 @@ pub enum TokenKind {
 }
 
 const EOF_CHAR: char = '\0';
+const CHUNK_SIZE: usize = 8 * 1024;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Token {
-    pub kind: TokenKind,
-    pub len: u32,
-    pub span: Span,
+    kind: TokenKind,
+    len: u32,
+    span: Span,
 }
 
 impl Token {
@@ -71,6 +84,18 @@ impl Token {
             span: Span { start, end },
         }
     }
+    pub fn kind(&self) -> TokenKind {
+        self.kind
+    }
+    pub fn len(&self) -> u32 {
+        self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    pub fn span(&self) -> Span {
+        self.span
+    }
 }
 
 pub struct Lexer<'a> {
@@ -83,15 +108,32 @@ pub struct Lexer<'a> {
 
 impl<'a> Lexer<'a> {
     pub fn new(input: &'a str) -> Self {
-        let mut lex = Self {
+        let mut lex = Self::cursor(input);
+        lex.tokenize();
+        lex
+    }
+
+    /// Tokenizes `reader` incrementally, see [`StreamLexer`].
+    pub fn from_reader<R: Read>(reader: R) -> StreamLexer<R> {
+        StreamLexer {
+            reader,
+            src: String::new(),
+            pending: Vec::new(),
+            pos: 0,
+            partial: None,
+            exhausted: false,
+            done: false,
+        }
+    }
+
+    fn cursor(input: &'a str) -> Self {
+        Self {
             src: input,
             chars: input.chars(),
             len_remaining: input.len(),
             tot_length: input.len(),
             tokens: Vec::new(),
-        };
-        lex.tokenize();
-        lex
+        }
     }
 
     fn pos_within_token(&self) -> u32 {
@@ -190,7 +232,7 @@ impl<'a> Lexer<'a> {
             '<' => TokenKind::Lt,
             '>' => TokenKind::Gt,
             ';' => TokenKind::Semi,
-            '0'..'9' => {
+            '0'..='9' => {
                 self.number();
                 let suffix_start = self.pos_within_token();
                 TokenKind::Literal {
@@ -216,7 +258,56 @@ impl<'a> Lexer<'a> {
         res
     }
 
-    pub fn tokenize(&mut self) {
+    /// Scans the rest of a token of `kind`, `len` bytes of which came
+    /// before this lexer's input, and returns its kind as of where it stops.
+    fn resume(&mut self, kind: TokenKind, len: u32) -> TokenKind {
+        match kind {
+            TokenKind::Ws => {
+                self.eat_while(|c| c.is_ascii_whitespace());
+                kind
+            }
+            TokenKind::Ident => {
+                self.eat_ident();
+                kind
+            }
+            TokenKind::Literal {
+                kind: LiteralKind::Int,
+                ..
+            } => {
+                self.number();
+                TokenKind::Literal {
+                    kind: LiteralKind::Int,
+                    suffix_start: len + self.pos_within_token(),
+                }
+            }
+            TokenKind::Literal {
+                kind: LiteralKind::Str { terminated: false },
+                ..
+            } => {
+                let terminated = self.is_str_terminated();
+                let suffix_start = len + self.pos_within_token();
+                if terminated {
+                    self.eat_ident();
+                }
+                let kind = LiteralKind::Str { terminated };
+                TokenKind::Literal { kind, suffix_start }
+            }
+            TokenKind::Literal {
+                kind: LiteralKind::Str { terminated: true },
+                ..
+            } => {
+                self.eat_ident();
+                kind
+            }
+            _ => kind,
+        }
+    }
+
+    pub fn tokens(&self) -> &[Token] {
+        &self.tokens
+    }
+
+    fn tokenize(&mut self) {
         loop {
             let token = self.advance_token();
             self.tokens.push(token);
@@ -228,6 +319,125 @@ impl<'a> Lexer<'a> {
     }
 }
 
+/// Lexer pulling its input from an [`io::Read`] in chunks, yielding tokens
+/// as soon as they are complete.
+///
+/// Text read so far is kept in a growing buffer, see [`StreamLexer::source`];
+/// spans are offsets into it, the same as if the whole input had been
+/// handed to [`Lexer::new`].
+pub struct StreamLexer<R> {
+    reader: R,
+    src: String,
+    // trailing bytes of a UTF-8 sequence split across two reads
+    pending: Vec<u8>,
+    pos: usize,
+    // token cut off at the end of the buffer: its kind so far and where
+    // scanning stopped, so the next chunk picks up from there
+    partial: Option<(TokenKind, usize)>,
+    exhausted: bool,
+    done: bool,
+}
+
+impl<R: Read> StreamLexer<R> {
+    pub fn source(&self) -> &str {
+        &self.src
+    }
+
+    fn fill(&mut self) -> io::Result<()> {
+        let mut chunk = [0; CHUNK_SIZE];
+        let n = loop {
+            match self.reader.read(&mut chunk) {
+                Ok(n) => break n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        };
+
+        if n == 0 {
+            self.exhausted = true;
+            if !self.pending.is_empty() {
+                return Err(invalid_utf8());
+            }
+            return Ok(());
+        }
+
+        self.pending.extend_from_slice(&chunk[..n]);
+        let valid = match std::str::from_utf8(&self.pending) {
+            Ok(s) => s.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => return Err(invalid_utf8()),
+        };
+        let text = std::str::from_utf8(&self.pending[..valid]).expect("checked above");
+        self.src.push_str(text);
+        self.pending.drain(..valid);
+        Ok(())
+    }
+}
+
+impl<R> StreamLexer<R> {
+    fn finish(&mut self, kind: TokenKind, end: usize) -> Token {
+        let start = self.pos;
+        self.pos = end;
+        Token::new(kind, (end - start) as u32, start, end)
+    }
+}
+
+impl<R: Read> Iterator for StreamLexer<R> {
+    type Item = io::Result<Token>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        loop {
+            if let Some((kind, scanned)) = self.partial {
+                let rest = &self.src[scanned..];
+                if !rest.is_empty() || self.exhausted {
+                    let mut cursor = Lexer::cursor(rest);
+                    let kind = cursor.resume(kind, (scanned - self.pos) as u32);
+                    let end = scanned + cursor.pos_within_token() as usize;
+                    if !cursor.is_eof() || self.exhausted {
+                        self.partial = None;
+                        return Some(Ok(self.finish(kind, end)));
+                    }
+                    self.partial = Some((kind, end));
+                }
+            } else {
+                let rest = &self.src[self.pos..];
+                if rest.is_empty() && self.exhausted {
+                    self.done = true;
+                    let end = self.src.len();
+                    return Some(Ok(Token::new(TokenKind::Eof, 0, end, end)));
+                }
+
+                if !rest.is_empty() {
+                    let t = Lexer::cursor(rest).advance_token();
+                    let end = self.pos + t.span.end;
+                    // a token running up to the end of the buffer might go
+                    // on in the next chunk
+                    if end < self.src.len() || self.exhausted {
+                        return Some(Ok(self.finish(t.kind, end)));
+                    }
+                    self.partial = Some((t.kind, end));
+                }
+            }
+
+            if let Err(e) = self.fill() {
+                self.done = true;
+                return Some(Err(e));
+            }
+        }
+    }
+}
+
+fn invalid_utf8() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        "stream did not contain valid UTF-8",
+    )
+}
+
 impl<'a> std::fmt::Debug for Lexer<'a> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         for t in &self.tokens {
@@ -263,6 +473,18 @@ mod tests {
                 suffix_start: 5,
             }
         );
+
+        let program = "90";
+        let lex = Lexer::new(program);
+
+        assert_eq!(lex.tokens.len(), 2);
+        assert_eq!(
+            lex.tokens.first().unwrap().kind,
+            TokenKind::Literal {
+                kind: LiteralKind::Int,
+                suffix_start: 2,
+            }
+        );
     }
 
     #[test]
@@ -301,6 +523,76 @@ mod tests {
         assert_eq!(second.len, 6);
     }
 
+    /// Hands out at most `chunk` bytes per read.
+    pub(super) struct Trickle<'a> {
+        pub(super) src: &'a [u8],
+        pub(super) chunk: usize,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = self.chunk.min(buf.len()).min(self.src.len());
+            buf[..n].copy_from_slice(&self.src[..n]);
+            self.src = &self.src[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_from_reader() {
+        let program = "func main() { jinbe größe = 12345; jet_pistol(\"one piece\"); }";
+        let lex = Lexer::new(program);
+
+        for chunk in [1, 2, 3, 7, CHUNK_SIZE] {
+            let reader = Trickle {
+                src: program.as_bytes(),
+                chunk,
+            };
+            let mut stream = Lexer::from_reader(reader);
+            let tokens: Vec<Token> = stream.by_ref().map(Result::unwrap).collect();
+
+            assert_eq!(tokens, lex.tokens);
+            assert_eq!(stream.source(), program);
+        }
+    }
+
+    #[test]
+    fn test_from_reader_long_tokens() {
+        let mut program = String::from("jinbe ");
+        program.push_str(&"a".repeat(3 * CHUNK_SIZE));
+        program.push_str(&" ".repeat(2 * CHUNK_SIZE));
+        program.push_str(&"9".repeat(2 * CHUNK_SIZE));
+        program.push_str(" \"");
+        program.push_str(&"ö".repeat(3 * CHUNK_SIZE));
+        program.push('"');
+        program.push_str(&"s".repeat(CHUNK_SIZE));
+        program.push_str("; \"");
+        program.push_str(&"x".repeat(5 * CHUNK_SIZE));
+        let lex = Lexer::new(&program);
+
+        for chunk in [1000, CHUNK_SIZE] {
+            let reader = Trickle {
+                src: program.as_bytes(),
+                chunk,
+            };
+            let tokens: Vec<Token> = Lexer::from_reader(reader).map(Result::unwrap).collect();
+
+            assert_eq!(tokens, lex.tokens);
+        }
+    }
+
+    #[test]
+    fn test_from_reader_invalid_utf8() {
+        let reader = Trickle {
+            src: b"jet_pistol(\"\xff\")",
+            chunk: 4,
+        };
+        let results: Vec<io::Result<Token>> = Lexer::from_reader(reader).collect();
+
+        let err = results.last().unwrap().as_ref().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
     #[test]
     fn test_program() {
         let program = "func main() { jet_pistol(\"one piece\"); }";
@@ -308,3 +600,51 @@ mod tests {
         assert_eq!(lex.tokens.len(), 16);
     }
 }
+
+// Needs `proptest = "1"` under [dev-dependencies] in Cargo.toml.
+#[cfg(test)]
+mod proptests {
+    use super::*;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn slices_reconstruct_input(src in any::<String>()) {
+            let lex = Lexer::new(&src);
+            let joined: String = lex.tokens.iter().map(|t| t.span.slice(&src)).collect();
+            prop_assert_eq!(joined, src);
+        }
+
+        #[test]
+        fn spans_sorted_and_disjoint(src in any::<String>()) {
+            let lex = Lexer::new(&src);
+            for pair in lex.tokens.windows(2) {
+                prop_assert!(pair[0].span.start <= pair[0].span.end);
+                prop_assert!(pair[0].span.end <= pair[1].span.start);
+            }
+        }
+
+        #[test]
+        fn len_matches_span(src in any::<String>()) {
+            let lex = Lexer::new(&src);
+            for t in &lex.tokens {
+                prop_assert_eq!(t.len as usize, t.span.len());
+            }
+        }
+
+        #[test]
+        fn stream_matches_batch(src in any::<String>(), chunk in 1usize..16) {
+            let reader = super::tests::Trickle { src: src.as_bytes(), chunk };
+            let streamed: Vec<Token> = Lexer::from_reader(reader).map(Result::unwrap).collect();
+            prop_assert_eq!(streamed, Lexer::new(&src).tokens);
+        }
+
+        #[test]
+        fn program_like_roundtrip(src in r#"[a-z_0-9 \n\t(){}\[\];,.+*=<>&|"-]{0,64}"#) {
+            let lex = Lexer::new(&src);
+            let joined: String = lex.tokens.iter().map(|t| t.span.slice(&src)).collect();
+            prop_assert_eq!(joined, src.as_str());
+            prop_assert_eq!(lex.tokens.last().unwrap().kind, TokenKind::Eof);
+        }
+    }
+}
@@ -1,2 +1,15 @@
-pub mod lexer;
-pub mod parser;
+mod lexer;
+mod parser;
+
+pub mod diagnostics;
+pub mod generate;
+
+/// Turning source text into tokens.
+pub mod lex {
+    pub use crate::lexer::{Lexer, LiteralKind, Span, StreamLexer, Token, TokenKind};
+}
+
+/// Turning tokens into a syntax tree.
+pub mod parse {
+    pub use crate::parser::{ParseError, Parser};
+}
@@ -1,15 +1,252 @@
-use std::{fs::File, io::Read};
+use std::{
+    fs::{self, File},
+    io::{self, Read},
+    path::Path,
+    process,
+};
 
-use luffy::lexer::Lexer;
-use luffy::parser::Parser;
+use luffy::diagnostics::{ColorChoice, Diagnostic, Emitter, ErrorFormat, SourceFile};
+use luffy::generate::{mutate, GenConfig, Generator, Mutation, MAX_DEPTH};
+use luffy::lex::{Lexer, LiteralKind, Token, TokenKind};
+use luffy::parse::Parser;
+
+// Exit codes are a contract with scripts wrapping the CLI, keep them stable.
+// Internal compiler errors are panics, which already exit with 101.
+const EXIT_OK: i32 = 0;
+const EXIT_DIAGNOSTICS: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+
+const USAGE: &str = "\
+usage: luffy [--color=auto|always|never] [--error-format=human|short] [command]
+
+commands:
+    lex <path|->
+    generate-corpus [--stmts=N] [--max-depth=N] [--no-arith] [--no-strings] [dir] [count] [seed]";
 
 fn main() {
+    let mut color = ColorChoice::Auto;
+    let mut format = ErrorFormat::Human;
+    let mut args: Vec<String> = Vec::new();
+
+    for arg in std::env::args().skip(1) {
+        if let Some(value) = arg.strip_prefix("--color=") {
+            color = match value {
+                "auto" => ColorChoice::Auto,
+                "always" => ColorChoice::Always,
+                "never" => ColorChoice::Never,
+                _ => process::exit(usage(&format!("unknown color choice `{}`", value))),
+            };
+        } else if let Some(value) = arg.strip_prefix("--error-format=") {
+            format = match value {
+                "human" => ErrorFormat::Human,
+                "short" => ErrorFormat::Short,
+                _ => process::exit(usage(&format!("unknown error format `{}`", value))),
+            };
+        } else if args.is_empty() && arg.starts_with("--") {
+            // options after the subcommand are the subcommand's to check
+            process::exit(usage(&format!("unknown option `{}`", arg)));
+        } else {
+            args.push(arg);
+        }
+    }
+
+    let emitter = Emitter::new(color, format);
+    let code = match args.first().map(String::as_str) {
+        None => run(&emitter),
+        Some("lex") => lex(&emitter, &args[1..]),
+        Some("generate-corpus") => generate_corpus(&emitter, &args[1..]),
+        Some(other) => usage(&format!("unknown subcommand `{}`", other)),
+    };
+    process::exit(code);
+}
+
+fn usage(msg: &str) -> i32 {
+    eprintln!("error: {}", msg);
+    eprintln!("{}", USAGE);
+    EXIT_USAGE
+}
+
+/// Closes a failing invocation with the one-line summary.
+fn abort(emitter: &Emitter, errors: usize) -> i32 {
+    emitter.summary(errors);
+    EXIT_DIAGNOSTICS
+}
+
+fn run(emitter: &Emitter) -> i32 {
+    let path = "examples/hello_world.lfy";
     let mut s = String::new();
-    let _ = File::open("examples/hello_world.lfy")
-        .expect("File not found")
-        .read_to_string(&mut s);
+    if let Err(e) = File::open(path).and_then(|mut f| f.read_to_string(&mut s)) {
+        let diag = Diagnostic::bare(format!("couldn't read: {}", e));
+        emitter.emit(&SourceFile::new(path, ""), &diag);
+        return abort(emitter, 1);
+    }
+    let file = SourceFile::new(path, &s);
     let lex = Lexer::new(&s);
     println!("{:?}", &lex);
     let parser = Parser::new();
     println!("Parser => {:?}", parser);
+
+    let mut errors = 0;
+    for t in lex.tokens() {
+        if let Some(message) = lex_error(t, &s) {
+            emitter.emit(&file, &Diagnostic::error(message, t.span()));
+            errors += 1;
+        }
+    }
+
+    if errors > 0 {
+        return abort(emitter, errors);
+    }
+    EXIT_OK
+}
+
+fn lex_error(t: &Token, src: &str) -> Option<String> {
+    match t.kind() {
+        TokenKind::Unknown => Some(format!("unknown start of token: {}", t.span().slice(src))),
+        TokenKind::Literal {
+            kind: LiteralKind::Str { terminated: false },
+            ..
+        } => Some("unterminated double quote string".to_string()),
+        _ => None,
+    }
+}
+
+/// `luffy lex <path|->`
+///
+/// Prints tokens as soon as they are complete, `-` reads from stdin.
+fn lex(emitter: &Emitter, args: &[String]) -> i32 {
+    let Some(path) = args.first().map(String::as_str) else {
+        return usage("missing input, pass a path or `-`");
+    };
+    if let Some(arg) = args.iter().find(|a| a.starts_with("--")) {
+        return usage(&format!("unknown option `{}`", arg));
+    }
+    if let Some(arg) = args.get(1) {
+        return usage(&format!("unexpected argument `{}`", arg));
+    }
+    let name = if path == "-" { "<stdin>" } else { path };
+    let reader: Box<dyn Read> = if path == "-" {
+        Box::new(io::stdin().lock())
+    } else {
+        match File::open(path) {
+            Ok(f) => Box::new(f),
+            Err(e) => {
+                let diag = Diagnostic::bare(format!("couldn't read: {}", e));
+                emitter.emit(&SourceFile::new(name, ""), &diag);
+                return abort(emitter, 1);
+            }
+        }
+    };
+
+    let mut stream = Lexer::from_reader(reader);
+    let mut bad = Vec::new();
+    while let Some(token) = stream.next() {
+        let t = match token {
+            Ok(t) => t,
+            Err(e) => {
+                let diag = Diagnostic::bare(format!("couldn't read: {}", e));
+                emitter.emit(&SourceFile::new(name, ""), &diag);
+                return abort(emitter, 1);
+            }
+        };
+        println!(
+            "Kind: {:?} => {:?}",
+            t.kind(),
+            t.span().slice(stream.source())
+        );
+        if let Some(message) = lex_error(&t, stream.source()) {
+            bad.push(Diagnostic::error(message, t.span()));
+        }
+    }
+
+    let file = SourceFile::new(name, stream.source());
+    for diag in &bad {
+        emitter.emit(&file, diag);
+    }
+    if !bad.is_empty() {
+        return abort(emitter, bad.len());
+    }
+    EXIT_OK
+}
+
+/// `luffy generate-corpus [--stmts=N] [--max-depth=N] [--no-arith] [--no-strings]
+/// [dir] [count] [seed]`
+///
+/// Writes `count` generated programs to `dir`, each followed by its
+/// operator-swap and constant-tweak mutants when they apply.
+fn generate_corpus(emitter: &Emitter, args: &[String]) -> i32 {
+    let mut base = GenConfig::default();
+    let mut positional: Vec<&str> = Vec::new();
+
+    for arg in args {
+        if let Some(value) = arg.strip_prefix("--stmts=") {
+            match value.parse() {
+                Ok(n) => base = base.with_stmts(n),
+                Err(_) => return usage("--stmts must be a number"),
+            }
+        } else if let Some(value) = arg.strip_prefix("--max-depth=") {
+            match value.parse() {
+                Ok(n) if n <= MAX_DEPTH => base = base.with_max_depth(n),
+                _ => {
+                    let msg = format!("--max-depth must be a number up to {}", MAX_DEPTH);
+                    return usage(&msg);
+                }
+            }
+        } else if arg == "--no-arith" {
+            base = base.with_arith(false);
+        } else if arg == "--no-strings" {
+            base = base.with_strings(false);
+        } else if arg.starts_with("--") {
+            return usage(&format!("unknown option `{}`", arg));
+        } else {
+            positional.push(arg);
+        }
+    }
+
+    if let Some(arg) = positional.get(3) {
+        return usage(&format!("unexpected argument `{}`", arg));
+    }
+
+    let dir = Path::new(positional.first().copied().unwrap_or("corpus"));
+    let count: u64 = match positional.get(1).map(|s| s.parse()) {
+        None => 100,
+        Some(Ok(n)) => n,
+        Some(Err(_)) => return usage("count must be a number"),
+    };
+    let seed: u64 = match positional.get(2).map(|s| s.parse()) {
+        None => 0,
+        Some(Ok(n)) => n,
+        Some(Err(_)) => return usage("seed must be a number"),
+    };
+
+    if let Err(e) = fs::create_dir_all(dir) {
+        let diag = Diagnostic::bare(format!("couldn't create directory: {}", e));
+        emitter.emit(&SourceFile::new(&dir.display().to_string(), ""), &diag);
+        return abort(emitter, 1);
+    }
+
+    for i in 0..count {
+        let program = Generator::new(base.with_seed(seed.wrapping_add(i))).program();
+        let mut files = vec![(dir.join(format!("{:04}.lfy", i)), program.clone())];
+
+        for (suffix, mutation) in [
+            ("swap", Mutation::SwapOperator),
+            ("tweak", Mutation::TweakConstant),
+        ] {
+            if let Some(mutant) = mutate(&program, mutation, seed.wrapping_add(i)) {
+                files.push((dir.join(format!("{:04}.{}.lfy", i, suffix)), mutant));
+            }
+        }
+
+        for (path, contents) in files {
+            if let Err(e) = fs::write(&path, contents) {
+                let diag = Diagnostic::bare(format!("couldn't write file: {}", e));
+                emitter.emit(&SourceFile::new(&path.display().to_string(), ""), &diag);
+                return abort(emitter, 1);
+            }
+        }
+    }
+
+    println!("Generated {} programs in {}", count, dir.display());
+    EXIT_OK
 }
@@ -3,9 +3,19 @@ use crate::lexer::Token;
 
 #[derive(Debug)]
 pub struct ParseError {
-    pub message: String,
-    pub span: Token,
+    message: String,
+    span: Token,
 }
+
+impl ParseError {
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+    pub fn span(&self) -> Token {
+        self.span
+    }
+}
+
 type PResult<T> = Result<T, ParseError>;
 
 #[derive(Debug)]
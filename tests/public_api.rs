@@ -0,0 +1,129 @@
+//! Pins the public surface of the crate: everything downstream tools are
+//! allowed to rely on is named here, so breaking it fails to compile, and
+//! every `pub` declaration is snapshotted in public-api.txt, so growing it
+//! fails the snapshot test.
+
+use std::{fs, path::Path};
+
+use luffy::diagnostics::{ColorChoice, Diagnostic, Emitter, ErrorFormat, SourceFile};
+use luffy::generate::{mutate, GenConfig, Generator, Mutation};
+use luffy::lex::{Lexer, LiteralKind, Span, StreamLexer, Token, TokenKind};
+use luffy::parse::{ParseError, Parser};
+
+#[test]
+fn test_lex_surface() {
+    let src = "jet_pistol(\"one piece\");";
+    let lex = Lexer::new(src);
+    let tokens: &[Token] = lex.tokens();
+
+    let first: Token = tokens[0];
+    let kind: TokenKind = first.kind();
+    let len: u32 = first.len();
+    let span: Span = first.span();
+    let _: (usize, usize, usize) = (span.start(), span.end(), span.len());
+    let _: (bool, bool) = (first.is_empty(), span.is_empty());
+    let _: &str = span.slice(src);
+    assert_eq!(kind, TokenKind::Ident);
+    assert_eq!(len, 10);
+
+    let literal = tokens[2].kind();
+    let is_str = match literal {
+        TokenKind::Literal {
+            kind: LiteralKind::Str { terminated },
+            suffix_start: _,
+        } => terminated,
+        _ => false,
+    };
+    assert!(is_str);
+
+    let mut stream: StreamLexer<&[u8]> = Lexer::from_reader(src.as_bytes());
+    let _: Option<std::io::Result<Token>> = stream.next();
+    let _: &str = stream.source();
+}
+
+#[test]
+fn test_parse_surface() {
+    let _: Parser = Parser::new();
+    let _: Option<fn(&ParseError) -> &str> = Some(ParseError::message);
+    let _: Option<fn(&ParseError) -> Token> = Some(ParseError::span);
+}
+
+#[test]
+fn test_diagnostics_surface() {
+    let emitter = Emitter::new(ColorChoice::Never, ErrorFormat::Short);
+    let diag = Diagnostic::bare("gone overboard");
+    let _: &str = diag.message();
+    let _: Option<Span> = diag.span();
+    let span = Lexer::new("$").tokens()[0].span();
+    let _ = Diagnostic::error("unknown start of token", span);
+    assert_eq!(
+        emitter.render(&SourceFile::new("main.lfy", ""), &diag),
+        "main.lfy: error: gone overboard\n"
+    );
+    let file = SourceFile::new("main.lfy", "$");
+    let _: &str = file.name();
+    let _: &str = file.src();
+    let _: fn(&Emitter, &SourceFile, &Diagnostic) = Emitter::emit;
+    let _: fn(&Emitter, usize) = Emitter::summary;
+}
+
+#[test]
+fn test_generate_surface() {
+    let config = GenConfig::default()
+        .with_seed(1)
+        .with_stmts(4)
+        .with_max_depth(2)
+        .with_arith(true)
+        .with_strings(false);
+    let program = Generator::new(config).program();
+    let _: Option<String> = mutate(&program, Mutation::SwapOperator, 1);
+    let _: Option<String> = mutate(&program, Mutation::TweakConstant, 1);
+}
+
+/// `pub` declaration lines of lib.rs and the modules it declares, test
+/// modules excluded.
+fn public_items() -> String {
+    let src = Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+    let lib = fs::read_to_string(src.join("lib.rs")).unwrap();
+    let mut files: Vec<String> = lib
+        .lines()
+        .filter_map(|l| l.trim_start_matches("pub ").strip_prefix("mod "))
+        .filter_map(|l| l.strip_suffix(';'))
+        .map(|m| format!("{}.rs", m))
+        .collect();
+    files.push("lib.rs".to_string());
+    files.sort();
+
+    let mut out = String::new();
+    for name in files {
+        let text = fs::read_to_string(src.join(&name)).unwrap();
+        for line in text.lines().take_while(|l| *l != "#[cfg(test)]") {
+            let line = line.trim();
+            if line.starts_with("pub ") {
+                out.push_str(&format!("{}: {}\n", name, line));
+            }
+        }
+    }
+    out
+}
+
+/// Set `LUFFY_BLESS=1` to rewrite the snapshot after an intended change.
+#[test]
+fn test_public_api_snapshot() {
+    let snapshot = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/public-api.txt");
+    let actual = public_items();
+
+    if std::env::var_os("LUFFY_BLESS").is_some() {
+        fs::write(&snapshot, &actual).unwrap();
+        return;
+    }
+
+    let expected = fs::read_to_string(&snapshot).unwrap_or_default();
+    assert!(
+        actual == expected,
+        "public API changed, rerun with LUFFY_BLESS=1 if intended\n\
+         --- expected\n{}\n+++ actual\n{}",
+        expected,
+        actual
+    );
+}